@@ -1,14 +1,14 @@
 //! Client header advertising available compression algorithms.
 
 use crate::headers::{HeaderName, HeaderValue, Headers, ToHeaderValues, ACCEPT_ENCODING};
-use crate::utils::sort_by_weight;
+use crate::utils::{ensure_valid_weight, parse_weight, sort_by_weight, Weighted};
 use crate::{
-    content::{ContentEncoding, Encoding, EncodingProposal},
+    content::{ContentEncoding, Encoding, EncodingProposal, Preference},
     headers::Header,
 };
 use crate::{Error, StatusCode};
 
-use std::fmt::{self, Debug, Write};
+use std::fmt::{self, Debug, Display, Write};
 use std::option;
 use std::slice;
 
@@ -40,17 +40,13 @@ use std::slice;
 /// # Ok(()) }
 /// ```
 pub struct AcceptEncoding {
-    wildcard: bool,
-    entries: Vec<EncodingProposal>,
+    entries: Vec<AcceptDirective>,
 }
 
 impl AcceptEncoding {
     /// Create a new instance of `AcceptEncoding`.
     pub fn new() -> Self {
-        Self {
-            entries: vec![],
-            wildcard: false,
-        }
+        Self { entries: vec![] }
     }
 
     /// Create an instance of `AcceptEncoding` from a `Headers` instance.
@@ -61,8 +57,6 @@ impl AcceptEncoding {
             None => return Ok(None),
         };
 
-        let mut wildcard = false;
-
         for value in headers {
             for part in value.as_str().trim().split(',') {
                 let part = part.trim();
@@ -70,35 +64,69 @@ impl AcceptEncoding {
                 // Handle empty strings, and wildcard directives.
                 if part.is_empty() {
                     continue;
-                } else if part == "*" {
-                    wildcard = true;
+                } else if part == "*" || part.starts_with("*;") {
+                    let weight = match part.splitn(2, ';').nth(1) {
+                        Some(raw) => Some(parse_weight(raw)?),
+                        None => None,
+                    };
+                    entries.push(AcceptDirective::new(Preference::Any, weight)?);
                     continue;
                 }
 
                 // Try and parse a directive from a str. If the directive is
                 // unkown we skip it.
                 if let Some(entry) = EncodingProposal::from_str(part)? {
-                    entries.push(entry);
+                    entries.push(entry.into());
                 }
             }
         }
 
-        Ok(Some(Self { entries, wildcard }))
+        Ok(Some(Self { entries }))
     }
 
     /// Push a directive into the list of entries.
     pub fn push(&mut self, prop: impl Into<EncodingProposal>) {
-        self.entries.push(prop.into());
+        self.entries.push(prop.into().into());
     }
 
     /// Returns `true` if a wildcard directive was passed.
     pub fn wildcard(&self) -> bool {
-        self.wildcard
+        self.entries.iter().any(|entry| entry.preference.is_wildcard())
     }
 
-    /// Set the wildcard directive.
+    /// Returns the effective `q=` weight of the wildcard directive (`1.0` when
+    /// it carries no `q=`), or `None` if no wildcard directive was passed.
+    pub fn wildcard_weight(&self) -> Option<f32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.preference.is_wildcard())
+            .map(|entry| entry.weight.unwrap_or(1.0))
+    }
+
+    /// Set the wildcard directive, using the default (unspecified) weight.
     pub fn set_wildcard(&mut self, wildcard: bool) {
-        self.wildcard = wildcard
+        self.entries.retain(|entry| !entry.preference.is_wildcard());
+        if wildcard {
+            self.entries.push(AcceptDirective {
+                preference: Preference::Any,
+                weight: None,
+            });
+        }
+    }
+
+    /// Set the wildcard directive with an explicit `q=` weight, or remove it
+    /// entirely when `weight` is `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `weight` is outside the `0..=1` range.
+    pub fn set_wildcard_weight(&mut self, weight: Option<f32>) -> crate::Result<()> {
+        self.entries.retain(|entry| !entry.preference.is_wildcard());
+        if let Some(weight) = weight {
+            self.entries
+                .push(AcceptDirective::new(Preference::Any, Some(weight))?);
+        }
+        Ok(())
     }
 
     /// Sort the header directives by weight.
@@ -112,30 +140,147 @@ impl AcceptEncoding {
 
     /// Determine the most suitable `Content-Type` encoding.
     ///
+    /// Per [RFC 7231 §5.3.4](https://tools.ietf.org/html/rfc7231#section-5.3.4), an
+    /// encoding with an effective weight of exactly `0` (whether rejected
+    /// explicitly, e.g. `gzip;q=0`, or via a `*;q=0` wildcard) is never
+    /// selected. `identity` is treated as acceptable unless it, or a
+    /// wildcard covering it, is explicitly assigned `q=0`. Ties in weight are
+    /// broken toward the stronger codec by each encoding's default weight
+    /// (brotli, then zstd, gzip, deflate and identity), independent of the
+    /// order of `available`; use
+    /// [`negotiate_with_preference`][Self::negotiate_with_preference] if the
+    /// server has its own ranking to fall back on instead.
+    ///
     /// # Errors
     ///
     /// If no suitable encoding is found, an error with the status of `406` will be returned.
     pub fn negotiate(&mut self, available: &[Encoding]) -> crate::Result<ContentEncoding> {
-        // Start by ordering the encodings.
         self.sort();
 
-        // Try and find the first encoding that matches.
-        for encoding in &self.entries {
-            if available.contains(&encoding) {
-                return Ok(encoding.into());
+        // After `sort()`, the position of the first entry matching `encoding`
+        // reflects the client's declared preference (ties broken by default
+        // weight, then toward the later-declared directive), independent of
+        // `available`'s order.
+        let order: Vec<Encoding> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.preference.encoding())
+            .collect();
+        let rank_of = |encoding: Encoding| {
+            order
+                .iter()
+                .position(|&candidate| candidate == encoding)
+                .unwrap_or(usize::MAX)
+        };
+
+        self.best_match(available, true, rank_of)
+            .map(Into::into)
+            .ok_or_else(not_acceptable)
+    }
+
+    /// Determine the most suitable `Content-Type` encoding, letting the
+    /// server express its own preference between equally-weighted options.
+    ///
+    /// The client's `q=` weights still take priority: `server_pref` is only
+    /// consulted to break ties between encodings the client weights equally
+    /// (including implicitly, by not mentioning either). Encodings the
+    /// client has rejected (an effective weight of `0`, see
+    /// [`negotiate`][Self::negotiate]) are never selected, regardless of
+    /// where they sit in `server_pref`.
+    ///
+    /// # Errors
+    ///
+    /// If no suitable encoding is found, an error with the status of `406` will be returned.
+    pub fn negotiate_with_preference(
+        &mut self,
+        server_pref: &[Encoding],
+    ) -> crate::Result<ContentEncoding> {
+        self.sort();
+
+        let rank_of = |encoding: Encoding| {
+            server_pref
+                .iter()
+                .position(|&candidate| candidate == encoding)
+                .unwrap_or(usize::MAX)
+        };
+
+        self.best_match(server_pref, false, rank_of)
+            .map(Into::into)
+            .ok_or_else(not_acceptable)
+    }
+
+    /// Pick the highest-weighted encoding out of `available`, breaking ties
+    /// between equally-weighted encodings by [`Encoding::default_weight`]
+    /// when `by_default_weight` is set (so the server leans toward stronger
+    /// codecs) and, only if that is also equal, by `rank_of` (lower rank wins).
+    fn best_match(
+        &self,
+        available: &[Encoding],
+        by_default_weight: bool,
+        rank_of: impl Fn(Encoding) -> usize,
+    ) -> Option<Encoding> {
+        let tie_weight = |encoding: Encoding| {
+            if by_default_weight {
+                encoding.default_weight()
+            } else {
+                0.0
+            }
+        };
+        let mut best: Option<(Encoding, f32, usize)> = None;
+        for &encoding in available {
+            let weight = self.effective_weight(encoding);
+            if weight <= 0.0 {
+                continue;
+            }
+
+            let rank = rank_of(encoding);
+            let is_better = match best {
+                Some((best_encoding, best_weight, best_rank)) => {
+                    weight > best_weight
+                        || (weight == best_weight
+                            && tie_weight(encoding) > tie_weight(best_encoding))
+                        || (weight == best_weight
+                            && tie_weight(encoding) == tie_weight(best_encoding)
+                            && rank < best_rank)
+                }
+                None => true,
+            };
+            if is_better {
+                best = Some((encoding, weight, rank));
             }
         }
+        best.map(|(encoding, _, _)| encoding)
+    }
+
+    /// Compute the effective `q=` weight of `encoding` according to this
+    /// header's explicit proposals, wildcard, and the `identity` default.
+    ///
+    /// Entries are scanned in their current order, so calling this after
+    /// [`sort`][Self::sort] means a higher-ranked directive (specific or
+    /// wildcard) always wins over a lower-ranked one of the same kind.
+    fn effective_weight(&self, encoding: Encoding) -> f32 {
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|entry| entry.preference == Preference::Specific(encoding))
+        {
+            return entry.weight.unwrap_or(1.0);
+        }
 
-        // If no encoding matches and wildcard is set, send whichever encoding we got.
-        if self.wildcard {
-            if let Some(encoding) = available.iter().next() {
-                return Ok(encoding.into());
+        if let Some(entry) = self.entries.iter().find(|entry| entry.preference.is_wildcard()) {
+            let wildcard = entry.weight.unwrap_or(1.0);
+            // An explicit `*;q=0` still rejects `identity` per RFC 7231.
+            if encoding == Encoding::Identity && wildcard > 0.0 {
+                return 1.0;
             }
+            return wildcard;
         }
 
-        let mut err = Error::new_adhoc("No suitable ContentEncoding found");
-        err.set_status(StatusCode::NotAcceptable);
-        Err(err)
+        if encoding == Encoding::Identity {
+            1.0
+        } else {
+            0.0
+        }
     }
 
     /// Sets the `Accept-Encoding` header.
@@ -154,20 +299,12 @@ impl AcceptEncoding {
     pub fn value(&self) -> HeaderValue {
         let mut output = String::new();
         for (n, directive) in self.entries.iter().enumerate() {
-            let directive: HeaderValue = directive.clone().into();
             match n {
                 0 => write!(output, "{}", directive).unwrap(),
                 _ => write!(output, ", {}", directive).unwrap(),
             };
         }
 
-        if self.wildcard {
-            match output.len() {
-                0 => write!(output, "*").unwrap(),
-                _ => write!(output, ", *").unwrap(),
-            }
-        }
-
         // SAFETY: the internal string is validated to be ASCII.
         unsafe { HeaderValue::from_bytes_unchecked(output.into()) }
     }
@@ -187,17 +324,99 @@ impl AcceptEncoding {
     }
 }
 
+/// A single directive in an `Accept-Encoding` header: a [`Preference`] (a
+/// concrete encoding, or the `*` wildcard) together with its `q=` weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptDirective {
+    preference: Preference,
+    weight: Option<f32>,
+}
+
+impl AcceptDirective {
+    fn new(preference: Preference, weight: Option<f32>) -> crate::Result<Self> {
+        if let Some(weight) = weight {
+            ensure_valid_weight(weight)?;
+        }
+        Ok(Self { preference, weight })
+    }
+
+    /// The preference this directive names: a specific encoding, or the wildcard.
+    pub fn preference(&self) -> Preference {
+        self.preference
+    }
+
+    /// The `q=` weight attached to this directive, if any was given.
+    pub fn weight(&self) -> Option<f32> {
+        self.weight
+    }
+}
+
+impl From<EncodingProposal> for AcceptDirective {
+    fn from(proposal: EncodingProposal) -> Self {
+        Self {
+            preference: Preference::Specific(proposal.encoding()),
+            weight: proposal.weight(),
+        }
+    }
+}
+
+impl Weighted for AcceptDirective {
+    fn weight(&self) -> Option<f32> {
+        self.weight
+    }
+
+    fn default_weight(&self) -> f32 {
+        self.preference
+            .encoding()
+            .map(|encoding| encoding.default_weight())
+            .unwrap_or(0.0)
+    }
+}
+
+impl PartialEq<Encoding> for AcceptDirective {
+    fn eq(&self, other: &Encoding) -> bool {
+        self.preference == *other
+    }
+}
+
+// `iter`/`iter_mut` yield `&AcceptDirective`, and Rust has no blanket
+// `&A: PartialEq<B>` from `A: PartialEq<B>`, so tests comparing an iterator
+// item directly against an `Encoding` (e.g. `assert_eq!(iter.next().unwrap(),
+// Encoding::Gzip)`) need this impl too.
+impl PartialEq<Encoding> for &AcceptDirective {
+    fn eq(&self, other: &Encoding) -> bool {
+        (*self).preference == *other
+    }
+}
+
+impl Display for AcceptDirective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.weight {
+            Some(weight) => write!(f, "{};q={}", self.preference, weight),
+            None => write!(f, "{}", self.preference),
+        }
+    }
+}
+
+/// Build the `406` error returned when no encoding in `negotiate`/
+/// `negotiate_with_preference` is acceptable to the client.
+fn not_acceptable() -> Error {
+    let mut err = Error::new_adhoc("No suitable ContentEncoding found");
+    err.set_status(StatusCode::NotAcceptable);
+    err
+}
+
 impl Header for AcceptEncoding {
     fn header_name(&self) -> HeaderName {
         ACCEPT_ENCODING
     }
     fn header_value(&self) -> HeaderValue {
-        self.header_value()
+        self.value()
     }
 }
 
 impl IntoIterator for AcceptEncoding {
-    type Item = EncodingProposal;
+    type Item = AcceptDirective;
     type IntoIter = IntoIter;
 
     #[inline]
@@ -209,7 +428,7 @@ impl IntoIterator for AcceptEncoding {
 }
 
 impl<'a> IntoIterator for &'a AcceptEncoding {
-    type Item = &'a EncodingProposal;
+    type Item = &'a AcceptDirective;
     type IntoIter = Iter<'a>;
 
     #[inline]
@@ -219,7 +438,7 @@ impl<'a> IntoIterator for &'a AcceptEncoding {
 }
 
 impl<'a> IntoIterator for &'a mut AcceptEncoding {
-    type Item = &'a mut EncodingProposal;
+    type Item = &'a mut AcceptDirective;
     type IntoIter = IterMut<'a>;
 
     #[inline]
@@ -231,11 +450,11 @@ impl<'a> IntoIterator for &'a mut AcceptEncoding {
 /// A borrowing iterator over entries in `AcceptEncoding`.
 #[derive(Debug)]
 pub struct IntoIter {
-    inner: std::vec::IntoIter<EncodingProposal>,
+    inner: std::vec::IntoIter<AcceptDirective>,
 }
 
 impl Iterator for IntoIter {
-    type Item = EncodingProposal;
+    type Item = AcceptDirective;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
@@ -250,11 +469,11 @@ impl Iterator for IntoIter {
 /// A lending iterator over entries in `AcceptEncoding`.
 #[derive(Debug)]
 pub struct Iter<'a> {
-    inner: slice::Iter<'a, EncodingProposal>,
+    inner: slice::Iter<'a, AcceptDirective>,
 }
 
 impl<'a> Iterator for Iter<'a> {
-    type Item = &'a EncodingProposal;
+    type Item = &'a AcceptDirective;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
@@ -269,11 +488,11 @@ impl<'a> Iterator for Iter<'a> {
 /// A mutable iterator over entries in `AcceptEncoding`.
 #[derive(Debug)]
 pub struct IterMut<'a> {
-    inner: slice::IterMut<'a, EncodingProposal>,
+    inner: slice::IterMut<'a, AcceptDirective>,
 }
 
 impl<'a> Iterator for IterMut<'a> {
-    type Item = &'a mut EncodingProposal;
+    type Item = &'a mut AcceptDirective;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
@@ -404,6 +623,37 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn parses_zstd_and_deflate() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(Encoding::Zstd);
+        accept.push(Encoding::Deflate);
+
+        let mut headers = Response::new(200);
+        accept.apply_header(&mut headers);
+
+        let accept = AcceptEncoding::from_headers(headers)?.unwrap();
+        let mut accept = accept.iter();
+        assert_eq!(accept.next().unwrap(), Encoding::Zstd);
+        assert_eq!(accept.next().unwrap(), Encoding::Deflate);
+        Ok(())
+    }
+
+    #[test]
+    fn default_weight_breaks_ties_toward_stronger_codecs() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.5))?);
+        accept.push(EncodingProposal::new(Encoding::Zstd, Some(0.5))?);
+        accept.push(EncodingProposal::new(Encoding::Brotli, Some(0.5))?);
+
+        accept.sort();
+        let mut accept = accept.iter();
+        assert_eq!(accept.next().unwrap(), Encoding::Brotli);
+        assert_eq!(accept.next().unwrap(), Encoding::Zstd);
+        assert_eq!(accept.next().unwrap(), Encoding::Gzip);
+        Ok(())
+    }
+
     #[test]
     fn negotiate() -> crate::Result<()> {
         let mut accept = AcceptEncoding::new();
@@ -440,4 +690,121 @@ mod test {
         assert_eq!(accept.negotiate(&[Encoding::Gzip])?, Encoding::Gzip);
         Ok(())
     }
+
+    #[test]
+    fn negotiate_breaks_ties_independent_of_available_order() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.5))?);
+        accept.push(EncodingProposal::new(Encoding::Brotli, Some(0.5))?);
+
+        // Brotli wins the tie on its default weight, even though
+        // `available` lists gzip first.
+        assert_eq!(
+            accept.negotiate(&[Encoding::Gzip, Encoding::Brotli])?,
+            Encoding::Brotli,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_breaks_explicit_weight_tie_toward_stronger_codec() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Zstd, Some(0.5))?);
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.5))?);
+
+        // Gzip was declared after zstd, so declaration order alone would
+        // pick gzip; `default_weight` should override that in favor of the
+        // stronger codec.
+        assert_eq!(
+            accept.negotiate(&[Encoding::Gzip, Encoding::Zstd])?,
+            Encoding::Zstd,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_rejects_explicit_q_zero() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.0))?);
+        accept.push(EncodingProposal::new(Encoding::Identity, Some(0.5))?);
+
+        assert_eq!(
+            accept.negotiate(&[Encoding::Gzip, Encoding::Identity])?,
+            Encoding::Identity,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_wildcard_q_zero_rejects_everything_but_named() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(1.0))?);
+        accept.push(EncodingProposal::new(Encoding::Identity, Some(0.5))?);
+        accept.set_wildcard_weight(Some(0.0))?;
+
+        assert_eq!(
+            accept.negotiate(&[Encoding::Gzip, Encoding::Identity, Encoding::Brotli])?,
+            Encoding::Gzip,
+        );
+
+        let mut accept = AcceptEncoding::new();
+        accept.set_wildcard_weight(Some(0.0))?;
+        let err = accept.negotiate(&[Encoding::Brotli]).unwrap_err();
+        assert_eq!(err.status(), 406);
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_with_preference_breaks_ties_using_server_order() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.5))?);
+        accept.push(EncodingProposal::new(Encoding::Brotli, Some(0.5))?);
+
+        // The client declared gzip first, but the server prefers brotli.
+        assert_eq!(
+            accept.negotiate_with_preference(&[Encoding::Brotli, Encoding::Gzip])?,
+            Encoding::Brotli,
+        );
+
+        // Client weight still wins over server preference when it differs.
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.9))?);
+        accept.push(EncodingProposal::new(Encoding::Brotli, Some(0.1))?);
+        assert_eq!(
+            accept.negotiate_with_preference(&[Encoding::Brotli, Encoding::Gzip])?,
+            Encoding::Gzip,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn wildcard_participates_in_sort() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Gzip, Some(0.1))?);
+        accept.set_wildcard_weight(Some(0.5))?;
+
+        accept.sort();
+        let mut iter = accept.iter();
+
+        let wildcard = iter.next().unwrap();
+        assert!(wildcard.preference().is_wildcard());
+        assert_eq!(wildcard.weight(), Some(0.5));
+
+        let gzip = iter.next().unwrap();
+        assert_eq!(gzip.preference(), Preference::Specific(Encoding::Gzip));
+        Ok(())
+    }
+
+    #[test]
+    fn negotiate_honors_wildcard_weight() -> crate::Result<()> {
+        let mut accept = AcceptEncoding::new();
+        accept.push(EncodingProposal::new(Encoding::Identity, Some(0.5))?);
+        accept.set_wildcard_weight(Some(0.9))?;
+
+        assert_eq!(
+            accept.negotiate(&[Encoding::Identity, Encoding::Brotli])?,
+            Encoding::Brotli,
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,258 @@
+//! The synchronous compression/decompression backends shared by
+//! [`ContentEncoder`][crate::content::ContentEncoder] and
+//! [`ContentDecoder`][crate::content::ContentDecoder].
+
+use std::io::{self, Read, Write};
+
+use brotli::{CompressorWriter, Decompressor as BrotliDecoder};
+use flate2::write::{GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder};
+use flate2::Compression;
+
+use crate::content::Encoding;
+
+/// The compression level applied by a [`ContentEncoder`][crate::content::ContentEncoder],
+/// from `0` (fastest, largest output) to `9` (slowest, smallest output).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quality(u32);
+
+impl Quality {
+    /// Create a new `Quality`, clamping the level to the `0..=9` range.
+    pub fn new(level: u32) -> Self {
+        Self(level.min(9))
+    }
+
+    fn as_flate2(self) -> Compression {
+        Compression::new(self.0)
+    }
+
+    fn as_brotli(self) -> u32 {
+        // Brotli's quality range is also `0..=9` for our purposes, even
+        // though the format technically supports up to 11.
+        self.0
+    }
+}
+
+impl Default for Quality {
+    /// The default quality, `6`, matches the default used by `flate2`.
+    fn default() -> Self {
+        Self(6)
+    }
+}
+
+/// A single-codec compressor or decompressor, buffered entirely in memory.
+///
+/// Each variant owns an in-memory `Vec<u8>` sink; bytes written through the
+/// codec accumulate there and are drained out by the caller after each
+/// chunk, which keeps [`ContentEncoder`][crate::content::ContentEncoder] and
+/// [`ContentDecoder`][crate::content::ContentDecoder] free of any
+/// codec-specific logic.
+pub(crate) enum Codec {
+    GzipEncode(GzEncoder<Vec<u8>>),
+    GzipDecode(GzDecoder<Vec<u8>>),
+    DeflateEncode(ZlibEncoder<Vec<u8>>),
+    DeflateDecode(ZlibDecoder<Vec<u8>>),
+    BrotliEncode(CompressorWriter<Vec<u8>>),
+    // Brotli's `Decompressor` wraps a `Read`, not a `Write`, so unlike the
+    // other decoders it can't be fed incrementally through `write_all`;
+    // instead, `push` just accumulates the raw compressed bytes here and
+    // `finish` runs them through a single `Decompressor` in one pass.
+    BrotliDecode(Vec<u8>),
+    ZstdEncode(Box<zstd::stream::write::Encoder<'static, Vec<u8>>>),
+    ZstdDecode(Box<zstd::stream::write::Decoder<'static, Vec<u8>>>),
+    Identity,
+}
+
+impl Codec {
+    /// Create an encoder for `encoding` at the given `quality`.
+    pub(crate) fn encoder(encoding: Encoding, quality: Quality) -> io::Result<Self> {
+        Ok(match encoding {
+            Encoding::Gzip => Self::GzipEncode(GzEncoder::new(Vec::new(), quality.as_flate2())),
+            Encoding::Deflate => {
+                Self::DeflateEncode(ZlibEncoder::new(Vec::new(), quality.as_flate2()))
+            }
+            Encoding::Brotli => {
+                Self::BrotliEncode(CompressorWriter::new(Vec::new(), 4096, quality.as_brotli(), 22))
+            }
+            Encoding::Zstd => Self::ZstdEncode(Box::new(zstd::stream::write::Encoder::new(
+                Vec::new(),
+                // zstd reads level `0` as "use the default level", so map it to
+                // `1`, zstd's fastest positive level.
+                quality.0.max(1) as i32,
+            )?)),
+            Encoding::Identity => Self::Identity,
+        })
+    }
+
+    /// Create a decoder for `encoding`.
+    pub(crate) fn decoder(encoding: Encoding) -> io::Result<Self> {
+        Ok(match encoding {
+            Encoding::Gzip => Self::GzipDecode(GzDecoder::new(Vec::new())),
+            Encoding::Deflate => Self::DeflateDecode(ZlibDecoder::new(Vec::new())),
+            Encoding::Brotli => Self::BrotliDecode(Vec::new()),
+            Encoding::Zstd => Self::ZstdDecode(Box::new(zstd::stream::write::Decoder::new(
+                Vec::new(),
+            )?)),
+            Encoding::Identity => Self::Identity,
+        })
+    }
+
+    /// Feed `input` through the codec, returning the newly produced bytes.
+    pub(crate) fn push(&mut self, input: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::GzipEncode(w) => {
+                w.write_all(input)?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::GzipDecode(w) => {
+                w.write_all(input)?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::DeflateEncode(w) => {
+                w.write_all(input)?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::DeflateDecode(w) => {
+                w.write_all(input)?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::BrotliEncode(w) => {
+                w.write_all(input)?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::BrotliDecode(buf) => {
+                buf.extend_from_slice(input);
+                Ok(Vec::new())
+            }
+            Self::ZstdEncode(w) => {
+                w.write_all(input)?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::ZstdDecode(w) => {
+                w.write_all(input)?;
+                // Unlike `flate2`'s `Write` impls, `zstd`'s decoder only
+                // copies decompressed bytes into the wrapped `Vec<u8>` on
+                // the *next* `write`/`flush`/`finish` call, so a `flush`
+                // here is required to actually drain what was just decoded.
+                w.flush()?;
+                Ok(std::mem::take(w.get_mut()))
+            }
+            Self::Identity => Ok(input.to_vec()),
+        }
+    }
+
+    /// Flush and finalize the codec, returning any remaining buffered bytes.
+    pub(crate) fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::GzipEncode(w) => w.finish(),
+            Self::GzipDecode(w) => w.finish(),
+            Self::DeflateEncode(w) => w.finish(),
+            Self::DeflateDecode(w) => w.finish(),
+            Self::BrotliEncode(mut w) => {
+                w.flush()?;
+                Ok(w.into_inner())
+            }
+            Self::BrotliDecode(buf) => {
+                let mut output = Vec::new();
+                BrotliDecoder::new(io::Cursor::new(buf), 4096).read_to_end(&mut output)?;
+                Ok(output)
+            }
+            Self::ZstdEncode(w) => w.finish(),
+            Self::ZstdDecode(w) => Ok(w.into_inner()),
+            Self::Identity => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(encoding: Encoding) -> io::Result<()> {
+        let input = b"the quick brown fox jumps over the lazy dog".repeat(64);
+
+        let mut encoder = Codec::encoder(encoding, Quality::default())?;
+        let mut compressed = encoder.push(&input)?;
+        compressed.extend(encoder.finish()?);
+
+        let mut decoder = Codec::decoder(encoding)?;
+        let mut decompressed = decoder.push(&compressed)?;
+        decompressed.extend(decoder.finish()?);
+
+        assert_eq!(decompressed, input);
+        Ok(())
+    }
+
+    #[test]
+    fn gzip_round_trip() -> io::Result<()> {
+        round_trip(Encoding::Gzip)
+    }
+
+    #[test]
+    fn deflate_round_trip() -> io::Result<()> {
+        round_trip(Encoding::Deflate)
+    }
+
+    #[test]
+    fn brotli_round_trip() -> io::Result<()> {
+        round_trip(Encoding::Brotli)
+    }
+
+    #[test]
+    fn zstd_round_trip() -> io::Result<()> {
+        round_trip(Encoding::Zstd)
+    }
+
+    #[test]
+    fn identity_round_trip() -> io::Result<()> {
+        round_trip(Encoding::Identity)
+    }
+
+    #[test]
+    fn chained_encodings_decode_in_reverse_order() -> io::Result<()> {
+        let input = b"the quick brown fox jumps over the lazy dog".repeat(64);
+
+        // Apply gzip, then brotli on top, exactly as `Content-Encoding: gzip, br` means.
+        let mut gzip = Codec::encoder(Encoding::Gzip, Quality::default())?;
+        let mut gzipped = gzip.push(&input)?;
+        gzipped.extend(gzip.finish()?);
+
+        let mut brotli = Codec::encoder(Encoding::Brotli, Quality::default())?;
+        let mut doubly_encoded = brotli.push(&gzipped)?;
+        doubly_encoded.extend(brotli.finish()?);
+
+        // Undo in reverse: brotli first, then gzip.
+        let mut brotli = Codec::decoder(Encoding::Brotli)?;
+        let mut ungzipped = brotli.push(&doubly_encoded)?;
+        ungzipped.extend(brotli.finish()?);
+        assert_eq!(ungzipped, gzipped);
+
+        let mut gzip = Codec::decoder(Encoding::Gzip)?;
+        let mut original = gzip.push(&ungzipped)?;
+        original.extend(gzip.finish()?);
+        assert_eq!(original, input);
+        Ok(())
+    }
+
+    #[test]
+    fn brotli_decode_accumulates_across_multiple_chunks() -> io::Result<()> {
+        // Exercises `BrotliDecode`'s buffering with more than one `push`
+        // call, so a bug that drops or reorders bytes across chunk
+        // boundaries shows up as corrupted output rather than passing by
+        // accident on a single chunk.
+        let input = b"the quick brown fox jumps over the lazy dog".repeat(256);
+
+        let mut encoder = Codec::encoder(Encoding::Brotli, Quality::default())?;
+        let mut compressed = encoder.push(&input)?;
+        compressed.extend(encoder.finish()?);
+
+        let mut decoder = Codec::decoder(Encoding::Brotli)?;
+        let mut decompressed = Vec::new();
+        for chunk in compressed.chunks(37) {
+            decompressed.extend(decoder.push(chunk)?);
+        }
+        decompressed.extend(decoder.finish()?);
+
+        assert_eq!(decompressed, input);
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+//! Server header declaring which encoding was applied to a body.
+
+use crate::content::Encoding;
+use crate::headers::{HeaderName, HeaderValue, Header, Headers, ToHeaderValues, CONTENT_ENCODING};
+
+use std::option;
+
+/// Server header declaring which encoding was applied to a body.
+///
+/// # Specifications
+///
+/// - [RFC 7231, section 3.1.2.2: Content-Encoding](https://tools.ietf.org/html/rfc7231#section-3.1.2.2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContentEncoding {
+    encoding: Encoding,
+}
+
+impl ContentEncoding {
+    /// Create a new instance of `ContentEncoding`.
+    pub fn new(encoding: Encoding) -> Self {
+        Self { encoding }
+    }
+
+    /// The encoding that was applied.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Sets the `Content-Encoding` header.
+    pub fn apply(&self, mut headers: impl AsMut<Headers>) {
+        headers
+            .as_mut()
+            .insert(CONTENT_ENCODING, self.header_value());
+    }
+
+    /// Get the `HeaderName`.
+    pub fn name(&self) -> HeaderName {
+        CONTENT_ENCODING
+    }
+
+    /// Get the `HeaderValue`.
+    pub fn value(&self) -> HeaderValue {
+        self.encoding.into()
+    }
+}
+
+impl From<Encoding> for ContentEncoding {
+    fn from(encoding: Encoding) -> Self {
+        Self::new(encoding)
+    }
+}
+
+impl PartialEq<Encoding> for ContentEncoding {
+    fn eq(&self, other: &Encoding) -> bool {
+        self.encoding == *other
+    }
+}
+
+impl Header for ContentEncoding {
+    fn header_name(&self) -> HeaderName {
+        CONTENT_ENCODING
+    }
+    fn header_value(&self) -> HeaderValue {
+        self.value()
+    }
+}
+
+impl ToHeaderValues for ContentEncoding {
+    type Iter = option::IntoIter<HeaderValue>;
+    fn to_header_values(&self) -> crate::Result<Self::Iter> {
+        Ok(self.header_value().to_header_values().unwrap())
+    }
+}
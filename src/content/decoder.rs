@@ -0,0 +1,127 @@
+//! Transparently decode a body according to an incoming `Content-Encoding` header.
+
+use std::io;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+
+use futures_lite::io::AsyncRead;
+
+use crate::content::coding::Codec;
+use crate::content::Encoding;
+use crate::headers::{Headers, CONTENT_ENCODING};
+use crate::Body;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Decodes a single codec layer out of an `AsyncRead` stream.
+///
+/// [`ContentDecoder`] chains one of these per encoding listed in
+/// `Content-Encoding`, so this type stays ignorant of chaining.
+struct SingleDecoder {
+    source: Box<dyn AsyncRead + Send + Sync + Unpin>,
+    codec: Option<Codec>,
+    pending: io::Cursor<Vec<u8>>,
+    scratch: Vec<u8>,
+    done: bool,
+}
+
+impl SingleDecoder {
+    fn new(
+        source: Box<dyn AsyncRead + Send + Sync + Unpin>,
+        encoding: Encoding,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            source,
+            codec: Some(Codec::decoder(encoding)?),
+            pending: io::Cursor::new(Vec::new()),
+            scratch: vec![0; CHUNK_SIZE],
+            done: false,
+        })
+    }
+}
+
+impl AsyncRead for SingleDecoder {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            if self.pending.position() < self.pending.get_ref().len() as u64 {
+                let n = io::Read::read(&mut self.pending, buf)?;
+                return Poll::Ready(Ok(n));
+            }
+
+            if self.done {
+                return Poll::Ready(Ok(0));
+            }
+
+            let this = &mut *self;
+            let n = match Pin::new(&mut this.source).poll_read(cx, &mut this.scratch) {
+                Poll::Ready(result) => result?,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let codec = this.codec.as_mut().expect("codec is only taken on finish");
+            let produced = if n == 0 {
+                let codec = this.codec.take().unwrap();
+                this.done = true;
+                codec.finish()?
+            } else {
+                codec.push(&this.scratch[..n])?
+            };
+            this.pending = io::Cursor::new(produced);
+        }
+    }
+}
+
+/// Transparently decodes a body that was compressed with one or more
+/// codecs, as declared by its `Content-Encoding` header.
+///
+/// When more than one encoding was applied, `Content-Encoding` lists them in
+/// the order they were applied; `ContentDecoder` undoes them in the reverse
+/// order, the same way a browser would.
+pub struct ContentDecoder {
+    inner: Box<dyn AsyncRead + Send + Sync + Unpin>,
+}
+
+impl ContentDecoder {
+    /// Create a new `ContentDecoder`, undoing `encodings` (given in the
+    /// order they appear in `Content-Encoding`) in reverse.
+    pub fn new(source: Body, encodings: &[Encoding]) -> io::Result<Self> {
+        let mut stream: Box<dyn AsyncRead + Send + Sync + Unpin> = Box::new(source);
+        for &encoding in encodings.iter().rev() {
+            stream = Box::new(SingleDecoder::new(stream, encoding)?);
+        }
+        Ok(Self { inner: stream })
+    }
+
+    /// Create a new `ContentDecoder` by reading the codecs to undo directly
+    /// off the `Content-Encoding` header. If the header is absent, the
+    /// decoder passes `source` through unmodified.
+    pub fn from_headers(source: Body, headers: impl AsRef<Headers>) -> crate::Result<Self> {
+        let mut encodings = vec![];
+        if let Some(values) = headers.as_ref().get(CONTENT_ENCODING) {
+            for value in values {
+                for part in value.as_str().trim().split(',') {
+                    let part = part.trim();
+                    if !part.is_empty() {
+                        encodings.push(Encoding::from_str(part)?);
+                    }
+                }
+            }
+        }
+        Ok(Self::new(source, &encodings)?)
+    }
+}
+
+impl AsyncRead for ContentDecoder {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
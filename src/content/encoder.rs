@@ -0,0 +1,99 @@
+//! Apply a negotiated [`Encoding`] to a body, streaming the compressed bytes
+//! out as they are requested.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_lite::io::AsyncRead;
+
+use crate::content::coding::{Codec, Quality};
+use crate::content::Encoding;
+use crate::headers::{Headers, CONTENT_ENCODING, CONTENT_LENGTH};
+use crate::Body;
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Wraps a [`Body`] and compresses it using the given [`Encoding`] as it is read.
+///
+/// `ContentEncoder` implements `AsyncRead`, so it can be substituted for the
+/// original body wherever bytes are streamed out; [`ContentEncoder::apply_headers`]
+/// updates the accompanying `Content-Encoding`/`Content-Length` headers to match.
+pub struct ContentEncoder {
+    source: Body,
+    encoding: Encoding,
+    codec: Option<Codec>,
+    pending: io::Cursor<Vec<u8>>,
+    scratch: Vec<u8>,
+    done: bool,
+}
+
+impl ContentEncoder {
+    /// Create a new `ContentEncoder`, compressing `source` as `encoding` at the
+    /// default [`Quality`].
+    pub fn new(source: Body, encoding: Encoding) -> io::Result<Self> {
+        Self::with_quality(source, encoding, Quality::default())
+    }
+
+    /// Create a new `ContentEncoder`, compressing `source` as `encoding` at the
+    /// given [`Quality`].
+    pub fn with_quality(source: Body, encoding: Encoding, quality: Quality) -> io::Result<Self> {
+        Ok(Self {
+            source,
+            encoding,
+            codec: Some(Codec::encoder(encoding, quality)?),
+            pending: io::Cursor::new(Vec::new()),
+            scratch: vec![0; CHUNK_SIZE],
+            done: false,
+        })
+    }
+
+    /// The encoding this encoder applies.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Update `headers` to advertise this encoding, removing `Content-Length`:
+    /// a compressed body's length is not known up front, so the body must be
+    /// sent using chunked transfer-encoding instead.
+    pub fn apply_headers(&self, headers: &mut Headers) {
+        headers.insert(CONTENT_ENCODING, self.encoding.to_string());
+        headers.remove(CONTENT_LENGTH);
+    }
+}
+
+impl AsyncRead for ContentEncoder {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            // Serve whatever is already compressed before pulling in more input.
+            if self.pending.position() < self.pending.get_ref().len() as u64 {
+                let n = io::Read::read(&mut self.pending, buf)?;
+                return Poll::Ready(Ok(n));
+            }
+
+            if self.done {
+                return Poll::Ready(Ok(0));
+            }
+
+            let this = &mut *self;
+            let n = match Pin::new(&mut this.source).poll_read(cx, &mut this.scratch) {
+                Poll::Ready(result) => result?,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let codec = this.codec.as_mut().expect("codec is only taken on finish");
+            let produced = if n == 0 {
+                let codec = this.codec.take().unwrap();
+                this.done = true;
+                codec.finish()?
+            } else {
+                codec.push(&this.scratch[..n])?
+            };
+            this.pending = io::Cursor::new(produced);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! The encoding tokens shared between `Accept-Encoding` and `Content-Encoding`.
+
+use crate::headers::HeaderValue;
+use crate::{Error, StatusCode};
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// An encoding algorithm, as found in the `Accept-Encoding` and `Content-Encoding` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// The `gzip` encoding.
+    Gzip,
+    /// The `br` (Brotli) encoding.
+    Brotli,
+    /// The `zstd` (Zstandard) encoding.
+    Zstd,
+    /// The `deflate` (zlib) encoding.
+    Deflate,
+    /// The `identity` encoding, meaning the payload is left unmodified.
+    Identity,
+}
+
+impl Encoding {
+    /// Parse a single token, e.g. `"gzip"`, into an `Encoding`.
+    pub(crate) fn from_token(s: &str) -> Option<Self> {
+        match s {
+            "gzip" => Some(Self::Gzip),
+            "br" => Some(Self::Brotli),
+            "zstd" => Some(Self::Zstd),
+            "deflate" => Some(Self::Deflate),
+            "identity" => Some(Self::Identity),
+            _ => None,
+        }
+    }
+
+    /// The token used on the wire for this encoding.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+            Self::Deflate => "deflate",
+            Self::Identity => "identity",
+        }
+    }
+
+    /// The default quality weight used to break ties between proposals that
+    /// share the same explicit (or absent) `q=` value, so that negotiation
+    /// leans toward stronger codecs rather than purely declaration order.
+    pub(crate) fn default_weight(&self) -> f32 {
+        match self {
+            Self::Brotli => 1.1,
+            Self::Zstd => 1.0,
+            Self::Gzip => 0.9,
+            Self::Deflate => 0.8,
+            Self::Identity => 0.1,
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        Self::from_token(s.trim()).ok_or_else(|| {
+            let mut err = Error::new_adhoc("Invalid encoding");
+            err.set_status(StatusCode::BadRequest);
+            err
+        })
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl From<Encoding> for HeaderValue {
+    fn from(encoding: Encoding) -> Self {
+        // SAFETY: all encoding tokens are ASCII.
+        unsafe { HeaderValue::from_bytes_unchecked(encoding.as_str().as_bytes().to_vec()) }
+    }
+}
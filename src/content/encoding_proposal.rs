@@ -0,0 +1,118 @@
+//! A single proposed `Encoding`, as found in the `Accept-Encoding` header.
+
+use crate::content::Encoding;
+use crate::headers::HeaderValue;
+use crate::utils::{ensure_valid_weight, Weighted};
+
+use std::fmt::{self, Debug, Display};
+
+/// A proposed `Encoding` together with its optional `q=` weight.
+#[derive(Clone, PartialEq)]
+pub struct EncodingProposal {
+    encoding: Encoding,
+    weight: Option<f32>,
+}
+
+impl EncodingProposal {
+    /// Create a new instance of `EncodingProposal`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `weight` is outside the `0..=1` range.
+    pub fn new(encoding: impl Into<Encoding>, weight: Option<f32>) -> crate::Result<Self> {
+        if let Some(weight) = weight {
+            ensure_valid_weight(weight)?;
+        }
+        Ok(Self {
+            encoding: encoding.into(),
+            weight,
+        })
+    }
+
+    /// Parse a single `Accept-Encoding` directive, e.g. `"gzip;q=0.8"`.
+    ///
+    /// Returns `Ok(None)` if the encoding token is not recognized, so callers
+    /// can silently skip directives they don't understand.
+    pub fn from_str(s: &str) -> crate::Result<Option<Self>> {
+        let mut parts = s.split(';');
+        let token = parts.next().unwrap_or("").trim();
+
+        let encoding = match Encoding::from_token(token) {
+            Some(encoding) => encoding,
+            None => return Ok(None),
+        };
+
+        let weight = match parts.next() {
+            Some(raw) => Some(crate::utils::parse_weight(raw)?),
+            None => None,
+        };
+
+        Ok(Some(Self::new(encoding, weight)?))
+    }
+
+    /// The proposed encoding.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// The weight (`q=`) associated with this proposal, if any.
+    pub fn weight(&self) -> Option<f32> {
+        self.weight
+    }
+}
+
+impl Weighted for EncodingProposal {
+    fn weight(&self) -> Option<f32> {
+        self.weight
+    }
+
+    fn default_weight(&self) -> f32 {
+        self.encoding.default_weight()
+    }
+}
+
+impl From<Encoding> for EncodingProposal {
+    fn from(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            weight: None,
+        }
+    }
+}
+
+impl PartialEq<Encoding> for EncodingProposal {
+    fn eq(&self, other: &Encoding) -> bool {
+        self.encoding == *other
+    }
+}
+
+impl From<EncodingProposal> for HeaderValue {
+    fn from(proposal: EncodingProposal) -> Self {
+        match proposal.weight {
+            Some(weight) => {
+                let s = format!("{};q={}", proposal.encoding, weight);
+                // SAFETY: the string is built from ASCII parts only.
+                unsafe { HeaderValue::from_bytes_unchecked(s.into_bytes()) }
+            }
+            None => proposal.encoding.into(),
+        }
+    }
+}
+
+impl Display for EncodingProposal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.weight {
+            Some(weight) => write!(f, "{};q={}", self.encoding, weight),
+            None => write!(f, "{}", self.encoding),
+        }
+    }
+}
+
+impl Debug for EncodingProposal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncodingProposal")
+            .field("encoding", &self.encoding)
+            .field("weight", &self.weight)
+            .finish()
+    }
+}
@@ -0,0 +1,19 @@
+//! HTTP content negotiation and content-coding types.
+
+mod accept_encoding;
+mod coding;
+mod content_encoding;
+mod decoder;
+mod encoder;
+mod encoding;
+mod encoding_proposal;
+mod preference;
+
+pub use accept_encoding::{AcceptDirective, AcceptEncoding};
+pub use coding::Quality;
+pub use content_encoding::ContentEncoding;
+pub use decoder::ContentDecoder;
+pub use encoder::ContentEncoder;
+pub use encoding::Encoding;
+pub use encoding_proposal::EncodingProposal;
+pub use preference::Preference;
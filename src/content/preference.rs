@@ -0,0 +1,52 @@
+//! A single `Accept-Encoding` preference: a concrete encoding, or the `*` wildcard.
+
+use crate::content::Encoding;
+
+use std::fmt::{self, Display};
+
+/// A single preference named in an `Accept-Encoding` header: either a
+/// specific [`Encoding`], or the `*` wildcard covering any encoding not
+/// mentioned explicitly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preference {
+    /// The `*` wildcard.
+    Any,
+    /// A specific, named encoding.
+    Specific(Encoding),
+}
+
+impl Preference {
+    /// `true` if this is the `*` wildcard.
+    pub fn is_wildcard(&self) -> bool {
+        matches!(self, Self::Any)
+    }
+
+    /// The encoding this preference names, or `None` for the wildcard.
+    pub fn encoding(&self) -> Option<Encoding> {
+        match self {
+            Self::Any => None,
+            Self::Specific(encoding) => Some(*encoding),
+        }
+    }
+}
+
+impl From<Encoding> for Preference {
+    fn from(encoding: Encoding) -> Self {
+        Self::Specific(encoding)
+    }
+}
+
+impl PartialEq<Encoding> for Preference {
+    fn eq(&self, other: &Encoding) -> bool {
+        matches!(self, Self::Specific(encoding) if encoding == other)
+    }
+}
+
+impl Display for Preference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "*"),
+            Self::Specific(encoding) => write!(f, "{}", encoding),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Internal helpers shared across header parsing code.
+
+use crate::{Error, StatusCode};
+
+use std::cmp::Ordering;
+
+/// A proposal directive that carries an optional `q=` weight.
+pub(crate) trait Weighted {
+    /// The weight (`q=`) associated with this directive, if any.
+    fn weight(&self) -> Option<f32>;
+
+    /// A default weight used to break ties when two directives share the
+    /// same explicit (or absent) weight. Defaults to `0.0`, i.e. no
+    /// preference.
+    fn default_weight(&self) -> f32 {
+        0.0
+    }
+}
+
+/// Sort a list of proposals by their declared weight, descending.
+///
+/// A directive with no explicit weight sorts behind any directive that has
+/// one. When two directives share the same explicit weight, ties are broken
+/// by [`Weighted::default_weight`]; if that also ties, the directive that
+/// was declared later in the header is returned first.
+pub(crate) fn sort_by_weight<T: Weighted>(entries: &mut Vec<T>) {
+    entries.reverse();
+    entries.sort_by(|a, b| {
+        b.weight()
+            .partial_cmp(&a.weight())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                b.default_weight()
+                    .partial_cmp(&a.default_weight())
+                    .unwrap_or(Ordering::Equal)
+            })
+    });
+}
+
+/// Parse a `q=0.8`-style parameter into its weight, validating it lies in `0..=1`.
+pub(crate) fn parse_weight(s: &str) -> crate::Result<f32> {
+    let s = s.trim();
+    let invalid = || {
+        let mut err = Error::new_adhoc("Invalid q-value");
+        err.set_status(StatusCode::BadRequest);
+        err
+    };
+
+    let value = s.strip_prefix("q=").ok_or_else(invalid)?;
+    let weight: f32 = value.trim().parse().map_err(|_| invalid())?;
+    ensure_valid_weight(weight)?;
+    Ok(weight)
+}
+
+/// Validate that a weight lies within the `0..=1` range mandated by RFC 7231.
+pub(crate) fn ensure_valid_weight(weight: f32) -> crate::Result<()> {
+    if !(0.0..=1.0).contains(&weight) {
+        let mut err = Error::new_adhoc("Weight must be between 0 and 1");
+        err.set_status(StatusCode::BadRequest);
+        return Err(err);
+    }
+    Ok(())
+}